@@ -1,14 +1,268 @@
+use async_broadcast::{broadcast, Receiver as EventReceiver, RecvError, Sender as EventSender};
+use async_rustls::{
+    rustls::{
+        internal::pemfile, Certificate, NoClientAuth, PrivateKey,
+        ServerConfig as RustlsServerConfig,
+    },
+    TlsAcceptor,
+};
 use crate::{peer::Message, prelude::*, MessageSender};
-use futures::{executor::ThreadPool, lock::Mutex};
-use iroha_derive::log;
+use futures::{executor::ThreadPool, io::AsyncWriteExt, lock::Mutex};
+use iroha_derive::{log, Io};
 use iroha_network::prelude::*;
-use std::{convert::TryFrom, sync::Arc};
+use parity_scale_codec::{Decode, Encode};
+use rdkafka::{
+    config::ClientConfig,
+    producer::{FutureProducer, FutureRecord},
+};
+use std::{convert::TryFrom, fs::File, io::BufReader, sync::Arc, time::Duration};
 
 const QUERY_URI: &str = "/query";
 const INSTRUCTIONS_URI: &str = "/instruction";
 const BLOCKS_URI: &str = "/block";
-const OK: &[u8] = b"HTTP/1.1 200 OK\r\n\r\n";
-const INTERNAL_ERROR: &[u8] = b"HTTP/1.1 500 Internal Server Error\r\n\r\n";
+const EVENTS_URI: &str = "/events";
+
+/// Shared-secret auth for the write endpoints. `/instruction` and `/block`
+/// always require the secret once configured; `/query` only does if
+/// `require_for_queries` is set, since it has no side effects to protect.
+#[derive(Clone, Debug)]
+pub struct AuthConfig {
+    pub secret: String,
+    pub require_for_queries: bool,
+}
+
+/// Payload envelope for endpoints gated by `AuthConfig`. `Request` here is a
+/// minimal url+payload frame with no header support, so the shared secret
+/// travels inside the decoded payload instead of a transport header.
+#[derive(Clone, Debug, Io, Encode, Decode)]
+pub struct AuthenticatedPayload {
+    pub token: String,
+    pub payload: Vec<u8>,
+}
+
+/// Strips and checks the `AuthenticatedPayload` envelope, returning the
+/// inner payload once its token matches `auth.secret`.
+fn unwrap_authenticated_payload(auth: &AuthConfig, payload: Vec<u8>) -> Result<Vec<u8>, &'static str> {
+    let authenticated =
+        AuthenticatedPayload::try_from(payload).map_err(|_| "Malformed authenticated payload.")?;
+    if constant_time_eq(authenticated.token.as_bytes(), auth.secret.as_bytes()) {
+        Ok(authenticated.payload)
+    } else {
+        Err("Missing or invalid auth token.")
+    }
+}
+
+/// Compares two byte strings without branching on the first mismatch, so
+/// the time taken does not leak how many leading bytes of a guessed token
+/// were correct.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0_u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Escapes `"`, `\` and control characters so `input` can be interpolated
+/// into a JSON string literal without producing malformed JSON.
+fn escape_json(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Builds `Response`s carrying a real HTTP status line and, for non-2xx
+/// responses, a short JSON error body, instead of the blanket 500s
+/// `handle_request` used to return for every failure.
+struct ToriiResponse;
+
+impl ToriiResponse {
+    fn with_status(status_line: &str, error_message: Option<&str>) -> Response {
+        let mut response = format!("HTTP/1.1 {}\r\n\r\n", status_line).into_bytes();
+        if let Some(error_message) = error_message {
+            response.extend_from_slice(
+                format!(r#"{{"error":"{}"}}"#, escape_json(error_message)).as_bytes(),
+            );
+        }
+        response
+    }
+
+    fn ok(payload: Vec<u8>) -> Response {
+        let mut response = Self::with_status("200 OK", None);
+        response.extend(payload);
+        response
+    }
+
+    fn bad_request(error_message: &str) -> Response {
+        Self::with_status("400 Bad Request", Some(error_message))
+    }
+
+    fn unprocessable_entity(error_message: &str) -> Response {
+        Self::with_status("422 Unprocessable Entity", Some(error_message))
+    }
+
+    fn unauthorized(error_message: &str) -> Response {
+        Self::with_status("401 Unauthorized", Some(error_message))
+    }
+
+    fn service_unavailable(error_message: &str) -> Response {
+        Self::with_status("503 Service Unavailable", Some(error_message))
+    }
+}
+
+/// The capacity of the broadcast channel every `/events` subscriber draws
+/// from. Sized generously so a burst of commits does not make a slow
+/// subscriber miss events.
+const EVENTS_CHANNEL_CAPACITY: usize = 1024;
+
+/// A ledger event published on block commit. Subscribers connected to
+/// `/events` receive these instead of having to poll `/query`.
+#[derive(Clone, Debug, Io, Encode, Decode)]
+pub enum Event {
+    TransactionAccepted { hash: Hash, account_id: AccountId },
+    TransactionRejected {
+        hash: Hash,
+        account_id: AccountId,
+        reason: String,
+    },
+    BlockCommitted { hash: Hash },
+}
+
+/// Narrows a `/events` subscription to the events a single client cares
+/// about, so e.g. a submitter can block on the commit of exactly its own
+/// transaction instead of draining every event on the ledger.
+#[derive(Clone, Debug, Io, Encode, Decode)]
+pub enum EventFilter {
+    ByTransactionHash(Hash),
+    ByAccount(AccountId),
+}
+
+impl EventFilter {
+    fn matches(&self, event: &Event) -> bool {
+        match (self, event) {
+            (
+                EventFilter::ByTransactionHash(hash),
+                Event::TransactionAccepted { hash: event_hash, .. },
+            )
+            | (
+                EventFilter::ByTransactionHash(hash),
+                Event::TransactionRejected { hash: event_hash, .. },
+            ) => hash == event_hash,
+            (
+                EventFilter::ByAccount(account_id),
+                Event::TransactionAccepted {
+                    account_id: event_account_id,
+                    ..
+                },
+            )
+            | (
+                EventFilter::ByAccount(account_id),
+                Event::TransactionRejected {
+                    account_id: event_account_id,
+                    ..
+                },
+            ) => account_id == event_account_id,
+            _ => false,
+        }
+    }
+}
+
+/// Payload of a `/events` request: an optional filter narrowing which
+/// events the connection should be woken up for.
+#[derive(Clone, Debug, Default, Io, Encode, Decode)]
+pub struct EventsRequest {
+    pub filter: Option<EventFilter>,
+}
+
+/// Transport encryption settings for `Torii`. When set on a `Torii`, every
+/// accepted connection is upgraded to a rustls server session before
+/// `handle_request` ever sees it, so `/block`, `/instruction` and `/query`
+/// are all served over TLS.
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    pub certificate_chain_path: String,
+    pub private_key_path: String,
+}
+
+impl TlsConfig {
+    fn build_acceptor(&self) -> Result<TlsAcceptor, String> {
+        let certificate_chain = load_certificate_chain(&self.certificate_chain_path)?;
+        let private_key = load_private_key(&self.private_key_path)?;
+        let mut server_config = RustlsServerConfig::new(NoClientAuth::new());
+        server_config
+            .set_single_cert(certificate_chain, private_key)
+            .map_err(|e| format!("Failed to configure TLS certificate: {}", e))?;
+        Ok(TlsAcceptor::from(Arc::new(server_config)))
+    }
+}
+
+fn load_certificate_chain(path: &str) -> Result<Vec<Certificate>, String> {
+    let file =
+        File::open(path).map_err(|e| format!("Failed to open certificate chain {}: {}", path, e))?;
+    pemfile::certs(&mut BufReader::new(file))
+        .map_err(|_| format!("Failed to parse certificate chain {}.", path))
+}
+
+fn load_private_key(path: &str) -> Result<PrivateKey, String> {
+    let file =
+        File::open(path).map_err(|e| format!("Failed to open private key {}: {}", path, e))?;
+    let mut keys = pemfile::pkcs8_private_keys(&mut BufReader::new(file))
+        .map_err(|_| format!("Failed to parse private key {}.", path))?;
+    keys.pop()
+        .ok_or_else(|| format!("No private key found in {}.", path))
+}
+
+/// Configuration for the optional outbound Kafka pipeline that mirrors
+/// committed blocks (and their transactions) to downstream indexers. No
+/// `ProducerConfig` means Torii never touches Kafka.
+#[derive(Clone, Debug)]
+pub struct ProducerConfig {
+    pub brokers: String,
+    pub topic: String,
+    pub client_id: String,
+    pub buffer_size: usize,
+}
+
+impl ProducerConfig {
+    fn build_producer(&self) -> Result<FutureProducer, String> {
+        ClientConfig::new()
+            .set("bootstrap.servers", &self.brokers)
+            .set("client.id", &self.client_id)
+            .set("queue.buffering.max.messages", &self.buffer_size.to_string())
+            .create()
+            .map_err(|e| format!("Failed to configure Kafka producer: {}", e))
+    }
+}
+
+#[derive(Clone)]
+struct KafkaProducer {
+    producer: FutureProducer,
+    topic: String,
+}
+
+impl KafkaProducer {
+    /// Fires the send on `pool` so a slow or unreachable broker never blocks
+    /// network handling; failures are logged, never surfaced to the caller.
+    fn publish(&self, pool: &ThreadPool, payload: Vec<u8>) {
+        let producer = self.producer.clone();
+        let topic = self.topic.clone();
+        pool.spawn_ok(async move {
+            let record = FutureRecord::to(&topic).payload(&payload).key("");
+            if let Err((e, _)) = producer.send(record, Duration::from_secs(0)).await {
+                eprintln!("Failed to publish to Kafka topic {}: {}", topic, e);
+            }
+        });
+    }
+}
 
 pub struct Torii {
     url: String,
@@ -16,6 +270,12 @@ pub struct Torii {
     world_state_view: Arc<Mutex<WorldStateView>>,
     transaction_sender: Arc<Mutex<TransactionSender>>,
     message_sender: Arc<Mutex<MessageSender>>,
+    event_sender: EventSender<Event>,
+    // Keeps the events channel open while no `/events` subscriber is connected.
+    _event_receiver: EventReceiver<Event>,
+    tls: Option<TlsConfig>,
+    kafka_producer: Option<KafkaProducer>,
+    auth: Option<AuthConfig>,
 }
 
 impl Torii {
@@ -25,14 +285,74 @@ impl Torii {
         world_state_view: Arc<Mutex<WorldStateView>>,
         transaction_sender: TransactionSender,
         message_sender: MessageSender,
+        tls: Option<TlsConfig>,
+        producer_config: Option<ProducerConfig>,
+        auth: Option<AuthConfig>,
     ) -> Self {
+        let (mut event_sender, event_receiver) = broadcast(EVENTS_CHANNEL_CAPACITY);
+        // When full, drop the oldest event instead of blocking the publisher.
+        event_sender.set_overflow(true);
+        let kafka_producer = producer_config
+            .as_ref()
+            .map(|producer_config| {
+                producer_config.build_producer().map(|producer| KafkaProducer {
+                    producer,
+                    topic: producer_config.topic.clone(),
+                })
+            })
+            .transpose()
+            .expect("Failed to configure Kafka producer.");
         Torii {
             url: url.to_string(),
             world_state_view,
             pool_ref,
             transaction_sender: Arc::new(Mutex::new(transaction_sender)),
             message_sender: Arc::new(Mutex::new(message_sender)),
+            event_sender,
+            _event_receiver: event_receiver,
+            tls,
+            kafka_producer,
+            auth,
+        }
+    }
+
+    /// A handle to publish ledger events to every connected `/events`
+    /// subscriber, for use by the block commit path.
+    pub fn event_sender(&self) -> EventSender<Event> {
+        self.event_sender.clone()
+    }
+
+    /// Reports a committed block to the outside world: mirrors its
+    /// serialized bytes to Kafka (if configured) and fans its hash out to
+    /// every `/events` subscriber. `Torii` itself never calls this: it only
+    /// forwards peer gossip on `/block`, and it is the commit path that
+    /// decides when a block actually lands.
+    pub async fn notify_block_committed(&self, hash: Hash, block_payload: Vec<u8>) {
+        if let Some(kafka_producer) = &self.kafka_producer {
+            kafka_producer.publish(&self.pool_ref, block_payload);
         }
+        let _ = self.event_sender.broadcast(Event::BlockCommitted { hash }).await;
+    }
+
+    /// Fans a transaction's acceptance out to every `/events` subscriber, so
+    /// `EventFilter::ByTransactionHash`/`ByAccount` can wake a submitter as
+    /// soon as its own transaction lands, instead of it only ever seeing
+    /// `BlockCommitted`. Takes one transaction of a committed block;
+    /// `Torii` itself never calls this.
+    pub async fn notify_transaction_accepted(&self, hash: Hash, account_id: AccountId) {
+        let _ = self
+            .event_sender
+            .broadcast(Event::TransactionAccepted { hash, account_id })
+            .await;
+    }
+
+    /// Fans a transaction's rejection out to every `/events` subscriber, for
+    /// the same reason `notify_transaction_accepted` exists.
+    pub async fn notify_transaction_rejected(&self, hash: Hash, account_id: AccountId, reason: String) {
+        let _ = self
+            .event_sender
+            .broadcast(Event::TransactionRejected { hash, account_id, reason })
+            .await;
     }
 
     pub async fn start(&mut self) {
@@ -40,11 +360,22 @@ impl Torii {
         let world_state_view = Arc::clone(&self.world_state_view);
         let transaction_sender = Arc::clone(&self.transaction_sender);
         let message_sender = Arc::clone(&self.message_sender);
+        let event_sender = self.event_sender.clone();
+        let tls_acceptor = self
+            .tls
+            .as_ref()
+            .map(TlsConfig::build_acceptor)
+            .transpose()
+            .expect("Failed to configure TLS.");
+        let auth = self.auth.clone();
         let state = ToriiState {
             pool: self.pool_ref.clone(),
             world_state_view,
             transaction_sender,
             message_sender,
+            event_sender,
+            tls_acceptor,
+            auth,
         };
         Network::listen(Arc::new(Mutex::new(state)), url, handle_connection)
             .await
@@ -57,6 +388,9 @@ struct ToriiState {
     world_state_view: Arc<Mutex<WorldStateView>>,
     transaction_sender: Arc<Mutex<TransactionSender>>,
     message_sender: Arc<Mutex<MessageSender>>,
+    event_sender: EventSender<Event>,
+    tls_acceptor: Option<TlsAcceptor>,
+    auth: Option<AuthConfig>,
 }
 
 async fn handle_connection(
@@ -64,71 +398,200 @@ async fn handle_connection(
     stream: Box<dyn AsyncStream>,
 ) -> Result<(), String> {
     //TODO: Why network can't spawn new task?
+    let pool = state.lock().await.pool.clone();
     let state22 = Arc::clone(&state);
-    state.lock().await.pool.spawn_ok(async move {
-        Network::handle_message_async(state22, stream, handle_request)
-            .await
-            .expect("Failed to handle message.")
+    pool.spawn_ok(async move {
+        if let Err(e) = accept_and_dispatch(state22, stream).await {
+            eprintln!("Failed to handle connection: {}", e);
+        }
     });
     Ok(())
 }
 
+/// Establishes TLS (if configured) and reads the first request off `stream`,
+/// then dispatches it — all inside the already-spawned task, so a slow or
+/// stalled client (a hung handshake, a connection that never finishes
+/// sending) only ever blocks its own task, never the accept loop driving
+/// every other connection.
+async fn accept_and_dispatch(
+    state: State<ToriiState>,
+    stream: Box<dyn AsyncStream>,
+) -> Result<(), String> {
+    let tls_acceptor = state.lock().await.tls_acceptor.clone();
+    let mut stream: Box<dyn AsyncStream> = match tls_acceptor {
+        Some(tls_acceptor) => Box::new(
+            tls_acceptor
+                .accept(stream)
+                .await
+                .map_err(|e| format!("Failed to establish TLS session: {}", e))?,
+        ),
+        None => stream,
+    };
+    let request = Request::read_from(&mut stream)
+        .await
+        .map_err(|e| format!("Failed to read request: {}", e))?;
+    if request.url() == EVENTS_URI {
+        return handle_events_subscription(state, stream, request).await;
+    }
+    let response = handle_request(state, request).await?;
+    stream
+        .write_all(&response)
+        .await
+        .map_err(|e| format!("Failed to write response: {}", e))
+}
+
+/// Frames `payload` with a 4-byte big-endian length prefix, so a `/events`
+/// subscriber reading a stream of pushed events off the wire can tell where
+/// one event ends and the next begins.
+fn frame(payload: &[u8]) -> Vec<u8> {
+    let mut framed = (payload.len() as u32).to_be_bytes().to_vec();
+    framed.extend_from_slice(payload);
+    framed
+}
+
+/// Drives a `/events` connection for its entire lifetime. Acknowledges the
+/// subscription, then loops forever writing every event matching the
+/// requested filter back over `stream` as it is broadcast, until the
+/// subscriber disconnects or the broadcast channel itself is closed. Unlike
+/// `handle_request`, this never returns a single `Response` — the whole
+/// point of `/events` is that the connection stays open.
+async fn handle_events_subscription(
+    state: State<ToriiState>,
+    mut stream: Box<dyn AsyncStream>,
+    request: Request,
+) -> Result<(), String> {
+    let events_request = match EventsRequest::try_from(request.payload().to_vec()) {
+        Ok(events_request) => events_request,
+        Err(e) => {
+            eprintln!("Failed to decode events subscription: {}", e);
+            return stream
+                .write_all(&ToriiResponse::bad_request("Malformed events subscription payload."))
+                .await
+                .map_err(|e| format!("Failed to write response: {}", e));
+        }
+    };
+    let mut receiver = state.lock().await.event_sender.new_receiver();
+    stream
+        .write_all(&ToriiResponse::ok(Vec::new()))
+        .await
+        .map_err(|e| format!("Failed to acknowledge events subscription: {}", e))?;
+    loop {
+        match receiver.recv().await {
+            Ok(event) => {
+                let matches = events_request
+                    .filter
+                    .as_ref()
+                    .map_or(true, |filter| filter.matches(&event));
+                if matches {
+                    let payload: Vec<u8> = event.into();
+                    stream
+                        .write_all(&frame(&payload))
+                        .await
+                        .map_err(|e| format!("Subscriber disconnected: {}", e))?;
+                }
+            }
+            // A lagging subscriber misses events but stays subscribed; only a
+            // closed channel (the sender side dropped) ends the connection.
+            Err(RecvError::Overflowed(missed)) => {
+                eprintln!("Events subscriber lagged, missed {} events.", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
 #[log]
 async fn handle_request(state: State<ToriiState>, request: Request) -> Result<Response, String> {
+    let auth = state.lock().await.auth.clone();
+    let requires_auth = match (request.url(), &auth) {
+        (INSTRUCTIONS_URI, Some(_)) | (BLOCKS_URI, Some(_)) => true,
+        (QUERY_URI, Some(auth)) => auth.require_for_queries,
+        _ => false,
+    };
+    let payload = if requires_auth {
+        let auth = auth.as_ref().expect("requires_auth is only true when auth is configured");
+        match unwrap_authenticated_payload(auth, request.payload().to_vec()) {
+            Ok(payload) => payload,
+            Err(error_message) => return Ok(ToriiResponse::unauthorized(error_message)),
+        }
+    } else {
+        request.payload().to_vec()
+    };
     match request.url() {
-        INSTRUCTIONS_URI => match Transaction::try_from(request.payload().to_vec()) {
-            Ok(transaction) => {
-                state
+        INSTRUCTIONS_URI => match Transaction::try_from(payload) {
+            Ok(transaction) => match transaction.accept() {
+                Ok(transaction) => match state
                     .lock()
                     .await
                     .transaction_sender
                     .lock()
                     .await
-                    .start_send(transaction.accept().expect("Failed to accept transaction."))
-                    .map_err(|e| format!("{}", e))?;
-                Ok(OK.to_vec())
-            }
+                    .start_send(transaction)
+                {
+                    Ok(()) => Ok(ToriiResponse::ok(Vec::new())),
+                    Err(e) if e.is_full() => {
+                        eprintln!("Transaction queue is full, rejecting request.");
+                        Ok(ToriiResponse::service_unavailable("Transaction queue is full."))
+                    }
+                    Err(e) => Err(format!("{}", e)),
+                },
+                Err(e) => {
+                    eprintln!("Failed to accept transaction: {}", e);
+                    Ok(ToriiResponse::unprocessable_entity(&format!("{}", e)))
+                }
+            },
             Err(e) => {
                 eprintln!("Failed to decode transaction: {}", e);
-                Ok(INTERNAL_ERROR.to_vec())
+                Ok(ToriiResponse::bad_request("Malformed transaction payload."))
             }
         },
-        QUERY_URI => match QueryRequest::try_from(request.payload().to_vec()) {
+        QUERY_URI => match QueryRequest::try_from(payload) {
             Ok(request) => match request
                 .query
                 .execute(&*state.lock().await.world_state_view.lock().await)
             {
                 Ok(result) => {
-                    let mut response = OK.to_vec();
+                    let mut response = ToriiResponse::ok(Vec::new());
                     let result = &result;
                     response.append(&mut result.into());
                     Ok(response)
                 }
                 Err(e) => {
                     eprintln!("{}", e);
-                    Ok(INTERNAL_ERROR.to_vec())
+                    Ok(ToriiResponse::unprocessable_entity(&format!("{}", e)))
                 }
             },
             Err(e) => {
                 eprintln!("Failed to decode transaction: {}", e);
-                Ok(INTERNAL_ERROR.to_vec())
+                Ok(ToriiResponse::bad_request("Malformed query payload."))
             }
         },
-        BLOCKS_URI => match Message::try_from(request.payload().to_vec()) {
+        BLOCKS_URI => match Message::try_from(payload) {
             Ok(message) => {
-                state
+                match state
                     .lock()
                     .await
                     .message_sender
                     .lock()
                     .await
                     .start_send(message)
-                    .map_err(|e| format!("{}", e))?;
-                Ok(OK.to_vec())
+                {
+                    // This is peer consensus gossip (BlockCreated, BlockSigned,
+                    // ...), not a committed block — Kafka export and the
+                    // BlockCommitted event both belong to the commit path via
+                    // Torii::notify_block_committed, not here.
+                    Ok(()) => Ok(ToriiResponse::ok(Vec::new())),
+                    Err(e) if e.is_full() => {
+                        eprintln!("Peer message queue is full, rejecting request.");
+                        Ok(ToriiResponse::service_unavailable("Peer message queue is full."))
+                    }
+                    Err(e) => Err(format!("{}", e)),
+                }
             }
             Err(e) => {
                 eprintln!("Failed to decode peer message: {}", e);
-                Ok(INTERNAL_ERROR.to_vec())
+                Ok(ToriiResponse::bad_request("Malformed peer message payload."))
             }
         },
         non_supported_uri => panic!("URI not supported: {}.", &non_supported_uri),
@@ -150,18 +613,181 @@ mod tests {
         let config =
             Configuration::from_path(CONFIGURATION_PATH).expect("Failed to load configuration.");
         let torii_url = config.torii_url.to_string();
-        let (tx_tx, _) = mpsc::unbounded();
-        let (ms_tx, _) = mpsc::unbounded();
+        let (tx_tx, _) = mpsc::channel(100);
+        let (ms_tx, _) = mpsc::channel(100);
         let mut torii = Torii::new(
             &torii_url.clone(),
             ThreadPool::new().expect("Failed to build Thread Pool."),
             Arc::new(Mutex::new(WorldStateView::new())),
             tx_tx,
             ms_tx,
+            None,
+            None,
+            None,
         );
         task::spawn(async move {
             torii.start().await;
         });
         std::thread::sleep(Duration::from_millis(50));
     }
+
+    #[test]
+    fn event_filter_matches_by_transaction_hash_not_account() {
+        let hash: Hash = [1; 32];
+        let other_hash: Hash = [2; 32];
+        let account_id = AccountId::new("alice", "wonderland");
+        let event = Event::TransactionAccepted { hash, account_id: account_id.clone() };
+        assert!(EventFilter::ByTransactionHash(hash).matches(&event));
+        assert!(!EventFilter::ByTransactionHash(other_hash).matches(&event));
+        assert!(!EventFilter::ByAccount(AccountId::new("bob", "wonderland")).matches(&event));
+    }
+
+    #[test]
+    fn event_filter_matches_by_account() {
+        let hash: Hash = [1; 32];
+        let account_id = AccountId::new("alice", "wonderland");
+        let other_account_id = AccountId::new("bob", "wonderland");
+        let event = Event::TransactionRejected {
+            hash,
+            account_id: account_id.clone(),
+            reason: "not enough funds".to_string(),
+        };
+        assert!(EventFilter::ByAccount(account_id).matches(&event));
+        assert!(!EventFilter::ByAccount(other_account_id).matches(&event));
+    }
+
+    #[test]
+    fn frame_prefixes_payload_with_big_endian_length() {
+        let framed = frame(&[1, 2, 3]);
+        assert_eq!(framed, vec![0, 0, 0, 3, 1, 2, 3]);
+    }
+
+    #[async_std::test]
+    async fn notify_block_committed_reaches_events_subscriber() {
+        let (tx_tx, _) = mpsc::channel(100);
+        let (ms_tx, _) = mpsc::channel(100);
+        let torii = Torii::new(
+            "127.0.0.1:0",
+            ThreadPool::new().expect("Failed to build Thread Pool."),
+            Arc::new(Mutex::new(WorldStateView::new())),
+            tx_tx,
+            ms_tx,
+            None,
+            None,
+            None,
+        );
+        let mut receiver = torii.event_sender().new_receiver();
+        let hash: Hash = [7; 32];
+        torii.notify_block_committed(hash, Vec::new()).await;
+        match receiver.recv().await.expect("Failed to receive event.") {
+            Event::BlockCommitted { hash: received_hash } => assert_eq!(received_hash, hash),
+            other => panic!("Unexpected event: {:?}", other),
+        }
+    }
+
+    #[async_std::test]
+    async fn notify_transaction_accepted_wakes_a_by_transaction_hash_subscriber() {
+        let (tx_tx, _) = mpsc::channel(100);
+        let (ms_tx, _) = mpsc::channel(100);
+        let torii = Torii::new(
+            "127.0.0.1:0",
+            ThreadPool::new().expect("Failed to build Thread Pool."),
+            Arc::new(Mutex::new(WorldStateView::new())),
+            tx_tx,
+            ms_tx,
+            None,
+            None,
+            None,
+        );
+        let mut receiver = torii.event_sender().new_receiver();
+        let hash: Hash = [9; 32];
+        let account_id = AccountId::new("alice", "wonderland");
+        let filter = EventFilter::ByTransactionHash(hash);
+        torii.notify_transaction_accepted(hash, account_id.clone()).await;
+        let event = receiver.recv().await.expect("Failed to receive event.");
+        assert!(filter.matches(&event));
+        match event {
+            Event::TransactionAccepted { hash: received_hash, account_id: received_account_id } => {
+                assert_eq!(received_hash, hash);
+                assert_eq!(received_account_id, account_id);
+            }
+            other => panic!("Unexpected event: {:?}", other),
+        }
+    }
+
+    #[async_std::test]
+    async fn notify_transaction_rejected_wakes_a_by_account_subscriber() {
+        let (tx_tx, _) = mpsc::channel(100);
+        let (ms_tx, _) = mpsc::channel(100);
+        let torii = Torii::new(
+            "127.0.0.1:0",
+            ThreadPool::new().expect("Failed to build Thread Pool."),
+            Arc::new(Mutex::new(WorldStateView::new())),
+            tx_tx,
+            ms_tx,
+            None,
+            None,
+            None,
+        );
+        let mut receiver = torii.event_sender().new_receiver();
+        let hash: Hash = [9; 32];
+        let account_id = AccountId::new("alice", "wonderland");
+        let filter = EventFilter::ByAccount(account_id.clone());
+        torii
+            .notify_transaction_rejected(hash, account_id.clone(), "bad signature".to_string())
+            .await;
+        let event = receiver.recv().await.expect("Failed to receive event.");
+        assert!(filter.matches(&event));
+    }
+
+    #[test]
+    fn authenticated_payload_round_trips_through_the_codec() {
+        let authenticated = AuthenticatedPayload {
+            token: "secret".to_string(),
+            payload: vec![1, 2, 3],
+        };
+        let encoded = authenticated.encode();
+        let decoded = AuthenticatedPayload::decode(&mut encoded.as_slice())
+            .expect("Failed to decode authenticated payload.");
+        assert_eq!(decoded.token, authenticated.token);
+        assert_eq!(decoded.payload, authenticated.payload);
+    }
+
+    #[test]
+    fn unwrap_authenticated_payload_accepts_matching_token() {
+        let auth = AuthConfig { secret: "secret".to_string(), require_for_queries: false };
+        let authenticated =
+            AuthenticatedPayload { token: "secret".to_string(), payload: vec![4, 5, 6] };
+        let payload =
+            unwrap_authenticated_payload(&auth, authenticated.encode()).expect("Should authorize.");
+        assert_eq!(payload, vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn unwrap_authenticated_payload_rejects_wrong_token() {
+        let auth = AuthConfig { secret: "secret".to_string(), require_for_queries: false };
+        let authenticated =
+            AuthenticatedPayload { token: "wrong".to_string(), payload: vec![4, 5, 6] };
+        assert!(unwrap_authenticated_payload(&auth, authenticated.encode()).is_err());
+    }
+
+    #[test]
+    fn unwrap_authenticated_payload_rejects_malformed_envelope() {
+        let auth = AuthConfig { secret: "secret".to_string(), require_for_queries: false };
+        assert!(unwrap_authenticated_payload(&auth, vec![0xff]).is_err());
+    }
+
+    #[test]
+    fn escape_json_escapes_quotes_backslashes_and_newlines() {
+        let escaped = escape_json("bad \"query\"\\\nnext line");
+        assert_eq!(escaped, r#"bad \"query\"\\\nnext line"#);
+    }
+
+    #[test]
+    fn bad_request_response_body_is_valid_json() {
+        let response = ToriiResponse::bad_request("unexpected \"token\"");
+        let body = String::from_utf8(response).expect("Response should be valid UTF-8.");
+        assert!(body.starts_with("HTTP/1.1 400 Bad Request\r\n\r\n"));
+        assert!(body.ends_with(r#"{"error":"unexpected \"token\""}"#));
+    }
 }